@@ -1,17 +1,27 @@
+use crate::budget;
+use crate::content::{self, ContentKind};
+use crate::filter::PathFilter;
+use crate::format::OutputFormat;
 use crate::progress::ProgressTracker;
+use crate::source::resolve_source;
 use anyhow::Result;
-use reqwest::Client;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use std::fs::{self, File};
 use std::io::Write;
-use std::path::PathBuf;
-use tempfile::TempDir;
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
-use zip::ZipArchive;
 
 pub struct AmalgamationAction {
     pub url: String,
     pub output_pathname: PathBuf,
     pub verbose: bool,
+    pub includes: Vec<String>,
+    pub excludes: Vec<String>,
+    pub skip_binary: bool,
+    pub git_ref: Option<String>,
+    pub format: OutputFormat,
+    pub max_tokens: Option<u64>,
 }
 
 impl AmalgamationAction {
@@ -25,83 +35,42 @@ impl AmalgamationAction {
     pub async fn execute(&self) -> Result<()> {
         let progress = ProgressTracker::new();
 
-        // Stage 1: Resolving URL
-        self.log_progress(&progress, "Resolving repository URL...");
-        let resolved_url = resolve_url(&self.url);
-        if self.verbose {
-            println!("Resolved URL: {}", resolved_url);
-        }
+        // Stage 1: Resolving the source
+        self.log_progress(&progress, "Resolving repository source...");
+        let source = resolve_source(&self.url, self.git_ref.clone());
 
-        // Stage 2: Downloading
-        self.log_progress(&progress, "Downloading repository...");
-        let zip_content = self.download_repository(&resolved_url).await?;
+        // Stage 2: Fetching (download+extract, or a no-op for local dirs)
+        self.log_progress(&progress, "Fetching repository...");
+        let source_root = source.materialize().await?;
 
-        // Stage 3: Extracting
-        self.log_progress(&progress, "Extracting files...");
-        let temp_dir = TempDir::new()?;
-        self.extract_zip(&zip_content, &temp_dir)?;
-
-        // Stage 4: Analyzing files
+        // Stage 3: Analyzing files
         self.log_progress(&progress, "Analyzing files...");
-        let mut files = self.collect_all_files(&temp_dir)?;
+        let mut files = self.collect_all_files(source_root.path())?;
         files.sort();
 
-        // Stage 5: Writing to file
+        // Stage 4: Writing to file
         self.log_progress(&progress, "Writing files...");
-        write_files(&files, &self.output_pathname)?;
+        write_files(
+            &files,
+            &self.output_pathname,
+            self.skip_binary,
+            self.format,
+            source_root.path(),
+            self.max_tokens,
+        )?;
 
-        // Stage 6: Success
+        // Stage 5: Success
         progress.finish();
         Ok(())
     }
 
-    pub async fn download_repository(&self, resolved_url: &str) -> Result<Vec<u8>> {
-        let client = Client::new();
-        let archive_url = format!("{}/zipball/master", resolved_url);
-
-        let response = client.get(&archive_url).send().await?;
-
-        let content = response.bytes().await?;
-
-        if self.verbose {
-            let size_mb = content.len() as f64 / 1_048_576.0;
-            println!("Downloaded repository archive:");
-            println!("  URL: {}", archive_url);
-            println!("  Size: {:.2} MB", size_mb);
-            println!("  Target: {}", self.output_pathname.display());
-        }
-
-        Ok(content.to_vec())
-    }
-
-    pub fn extract_zip(&self, zip_content: &[u8], temp_dir: &TempDir) -> Result<()> {
-        let reader = std::io::Cursor::new(zip_content);
-        let mut archive = ZipArchive::new(reader)?;
-
-        for i in 0..archive.len() {
-            let mut file = archive.by_index(i)?;
-            let outpath = temp_dir.path().join(file.name());
-
-            if file.name().ends_with('/') {
-                fs::create_dir_all(&outpath)?;
-            } else {
-                if let Some(p) = outpath.parent() {
-                    fs::create_dir_all(p)?;
-                }
-                let mut outfile = File::create(&outpath)?;
-                std::io::copy(&mut file, &mut outfile)?;
-            }
-        }
-
-        Ok(())
-    }
-
-    fn collect_all_files(&self, dir: &TempDir) -> Result<Vec<PathBuf>> {
+    fn collect_all_files(&self, root: &Path) -> Result<Vec<PathBuf>> {
+        let filter = PathFilter::new(root, &self.includes, &self.excludes)?;
         let mut source_files = Vec::new();
 
-        for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        for entry in WalkDir::new(root).into_iter().filter_map(|e| e.ok()) {
             let path = entry.path();
-            if path.is_file() {
+            if path.is_file() && filter.is_allowed(root, path) {
                 source_files.push(path.to_path_buf());
             }
         }
@@ -115,11 +84,26 @@ pub fn resolve_url(url: &str) -> String {
     url.trim_end_matches(".git").to_string()
 }
 
-pub fn write_files(files: &[PathBuf], output_path: &PathBuf) -> Result<()> {
+pub fn write_files(
+    files: &[PathBuf],
+    output_path: &PathBuf,
+    skip_binary: bool,
+    format: OutputFormat,
+    root: &Path,
+    max_tokens: Option<u64>,
+) -> Result<()> {
     if let Some(parent) = output_path.parent() {
         fs::create_dir_all(parent)?;
     }
     let mut output_file = File::create(output_path)?;
+    let mut total_tokens: u64 = 0;
+
+    // What ends up written for a given file: decoded text, or the (mime,
+    // raw bytes) to emit as a base64 block.
+    enum Rendered {
+        Text(String),
+        Binary { mime: &'static str, bytes: Vec<u8> },
+    }
 
     for file_path in files {
         // Skip if not a regular file
@@ -127,20 +111,75 @@ pub fn write_files(files: &[PathBuf], output_path: &PathBuf) -> Result<()> {
             continue;
         }
 
-        // Write file header
-        writeln!(output_file, "// File: {}", file_path.display())?;
+        let relative_path = file_path.strip_prefix(root).unwrap_or(file_path);
 
-        // Read and write file content
-        match fs::read_to_string(file_path) {
-            Ok(content) => {
-                writeln!(output_file, "{}\n", content)?;
-            }
+        let bytes = match fs::read(file_path) {
+            Ok(bytes) => bytes,
             Err(e) => {
                 eprintln!(
                     "Warning: Could not read file {}: {}",
                     file_path.display(),
                     e
                 );
+                continue;
+            }
+        };
+
+        // Resolve what will actually be written before charging the token
+        // budget, so a binary file that's skipped never consumes budget,
+        // and a binary file that's kept is charged for its base64-encoded
+        // size rather than its (smaller) raw size.
+        let rendered = match content::classify(file_path, &bytes) {
+            // Re-validated as UTF-8 here in case the sniffed sample was
+            // clean but a later chunk of the file wasn't. If it's still not
+            // valid UTF-8, fall back to the binary path rather than
+            // dropping the file.
+            ContentKind::Text => match String::from_utf8(bytes) {
+                Ok(text) => Rendered::Text(text),
+                Err(e) => Rendered::Binary {
+                    mime: "application/octet-stream",
+                    bytes: e.into_bytes(),
+                },
+            },
+            ContentKind::Binary { mime } => Rendered::Binary { mime, bytes },
+        };
+
+        if matches!(rendered, Rendered::Binary { .. }) && skip_binary {
+            continue;
+        }
+
+        let estimated_tokens = match &rendered {
+            Rendered::Text(text) => budget::estimate_tokens(text.len() as u64),
+            Rendered::Binary { bytes, .. } => {
+                budget::estimate_tokens(budget::base64_encoded_len(bytes.len()) as u64)
+            }
+        };
+        if let Some(limit) = max_tokens {
+            if total_tokens + estimated_tokens > limit {
+                eprintln!(
+                    "Warning: stopping before {} — it would push the amalgamation past the {}-token budget ({} used so far)",
+                    file_path.display(),
+                    limit,
+                    total_tokens
+                );
+                break;
+            }
+        }
+        total_tokens += estimated_tokens;
+
+        match rendered {
+            Rendered::Text(text) => {
+                write!(output_file, "{}", format.header(relative_path, &text))?;
+                writeln!(output_file, "{}", text)?;
+                write!(output_file, "{}", format.footer(&text))?;
+                writeln!(output_file)?;
+            }
+            Rendered::Binary { mime, bytes } => {
+                let data_url = format!("data:{};base64,{}", mime, BASE64.encode(&bytes));
+                write!(output_file, "{}", format.header(relative_path, &data_url))?;
+                writeln!(output_file, "{}", data_url)?;
+                write!(output_file, "{}", format.footer(&data_url))?;
+                writeln!(output_file)?;
             }
         }
     }
@@ -177,4 +216,60 @@ mod tests {
             assert_eq!(resolve_url(input), expected);
         }
     }
+
+    fn write_and_read(
+        files: &[(&str, &[u8])],
+        format: OutputFormat,
+        max_tokens: Option<u64>,
+    ) -> String {
+        let root = tempfile::tempdir().unwrap();
+        let mut paths = Vec::new();
+        for (name, contents) in files {
+            let path = root.path().join(name);
+            fs::write(&path, contents).unwrap();
+            paths.push(path);
+        }
+
+        let output_path = root.path().join("out.txt");
+        write_files(&paths, &output_path, false, format, root.path(), max_tokens).unwrap();
+        fs::read_to_string(&output_path).unwrap()
+    }
+
+    #[test]
+    fn plain_format_interleaves_headers_and_contents_in_order() {
+        let output = write_and_read(
+            &[("a.rs", b"fn a() {}"), ("b.rs", b"fn b() {}")],
+            OutputFormat::Plain,
+            None,
+        );
+
+        assert_eq!(
+            output,
+            "// File: a.rs\nfn a() {}\n\n// File: b.rs\nfn b() {}\n\n"
+        );
+    }
+
+    #[test]
+    fn markdown_format_emits_base64_for_binary_files() {
+        let binary = [0u8, 159, 146, 150];
+        let output = write_and_read(&[("image.bin", &binary)], OutputFormat::Markdown, None);
+
+        assert!(output.starts_with("## image.bin\n```\n"));
+        assert!(output.contains("data:application/octet-stream;base64,"));
+        assert!(output.trim_end().ends_with("```"));
+    }
+
+    #[test]
+    fn max_tokens_stops_before_the_file_that_would_exceed_the_budget() {
+        // "fn a() {}" is 9 bytes -> 2 estimated tokens; a budget of 2 lets
+        // the first file through but must stop before the second.
+        let output = write_and_read(
+            &[("a.rs", b"fn a() {}"), ("b.rs", b"fn b() {}")],
+            OutputFormat::Plain,
+            Some(2),
+        );
+
+        assert!(output.contains("a.rs"));
+        assert!(!output.contains("b.rs"));
+    }
 }
@@ -0,0 +1,35 @@
+/// Estimates the token count of `byte_len` bytes of text using a cheap
+/// chars/4 heuristic. Good enough for budgeting an amalgamation against a
+/// model's context window without needing a real tokenizer.
+pub fn estimate_tokens(byte_len: u64) -> u64 {
+    byte_len / 4
+}
+
+/// The length of the base64 encoding of `raw_len` raw bytes, including `=`
+/// padding. Used to charge the token budget for what a binary entry
+/// actually costs in the output, which is ~33% larger than its raw size.
+pub fn base64_encoded_len(raw_len: usize) -> usize {
+    raw_len.div_ceil(3) * 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_tokens_divides_by_four() {
+        assert_eq!(estimate_tokens(0), 0);
+        assert_eq!(estimate_tokens(3), 0);
+        assert_eq!(estimate_tokens(4), 1);
+        assert_eq!(estimate_tokens(4000), 1000);
+    }
+
+    #[test]
+    fn base64_encoded_len_matches_known_sizes() {
+        assert_eq!(base64_encoded_len(0), 0);
+        assert_eq!(base64_encoded_len(1), 4);
+        assert_eq!(base64_encoded_len(2), 4);
+        assert_eq!(base64_encoded_len(3), 4);
+        assert_eq!(base64_encoded_len(4), 8);
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::Path;
+
+/// How a file's bytes should be represented in the amalgamated output.
+pub enum ContentKind {
+    Text,
+    Binary { mime: &'static str },
+}
+
+const IMAGE_MIME_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("webp", "image/webp"),
+    ("gif", "image/gif"),
+];
+
+/// Classifies a file's contents so `write_files` knows how to render it.
+///
+/// Image extensions are recognized directly; everything else is sniffed by
+/// scanning the first few KB for NUL bytes or invalid UTF-8, which is cheap
+/// and catches fonts, compiled artifacts, and other binary blobs.
+pub fn classify(path: &Path, contents: &[u8]) -> ContentKind {
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        let ext = ext.to_lowercase();
+        if let Some((_, mime)) = IMAGE_MIME_TYPES.iter().find(|(image_ext, _)| *image_ext == ext) {
+            return ContentKind::Binary { mime };
+        }
+    }
+
+    let sniff_len = contents.len().min(8192);
+    let sample = &contents[..sniff_len];
+    if sample.contains(&0) || std::str::from_utf8(sample).is_err() {
+        return ContentKind::Binary {
+            mime: "application/octet-stream",
+        };
+    }
+
+    ContentKind::Text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_image_extensions_as_binary_by_extension_alone() {
+        let kind = classify(Path::new("logo.png"), b"not actually a png");
+        assert!(matches!(kind, ContentKind::Binary { mime: "image/png" }));
+
+        let kind = classify(Path::new("photo.JPEG"), b"not actually a jpeg");
+        assert!(matches!(kind, ContentKind::Binary { mime: "image/jpeg" }));
+    }
+
+    #[test]
+    fn classifies_clean_utf8_as_text() {
+        let kind = classify(Path::new("main.rs"), b"fn main() {}\n");
+        assert!(matches!(kind, ContentKind::Text));
+    }
+
+    #[test]
+    fn sniffs_nul_bytes_and_invalid_utf8_as_binary() {
+        let kind = classify(Path::new("font.ttf"), &[0u8, 1, 2, 3]);
+        assert!(matches!(kind, ContentKind::Binary { .. }));
+
+        let kind = classify(Path::new("data.bin"), &[0xff, 0xfe, 0x00]);
+        assert!(matches!(kind, ContentKind::Binary { .. }));
+    }
+
+    #[test]
+    fn only_sniffs_the_first_8kb() {
+        // A clean sniff window followed by invalid UTF-8 past byte 8192 is
+        // classified as text here — `write_files` is responsible for
+        // falling back to the binary path once it re-validates the full
+        // contents.
+        let mut contents = vec![b'a'; 8192];
+        contents.extend_from_slice(&[0xff, 0xfe]);
+        assert!(matches!(
+            classify(Path::new("weird.txt"), &contents),
+            ContentKind::Text
+        ));
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// VCS metadata directories that are always skipped, regardless of what any
+/// discovered `.gitignore` says — real-world `.gitignore` files essentially
+/// never list `.git` themselves, since git doesn't need to ignore its own
+/// directory.
+const VCS_DIRS: &[&str] = &[".git", ".hg", ".svn"];
+
+/// Decides whether a path discovered under a repository root should be
+/// considered for amalgamation.
+///
+/// Combines any `.gitignore` files found in the tree with explicit
+/// `--include`/`--exclude` globs, layering them the way tools like exa or
+/// erdtree do: ignores first, then excludes, then includes.
+pub struct PathFilter {
+    gitignore: Option<Gitignore>,
+    includes: GlobSet,
+    excludes: GlobSet,
+}
+
+impl PathFilter {
+    pub fn new(root: &Path, includes: &[String], excludes: &[String]) -> Result<Self> {
+        Ok(Self {
+            gitignore: build_gitignore(root),
+            includes: build_glob_set(includes)?,
+            excludes: build_glob_set(excludes)?,
+        })
+    }
+
+    /// Returns `true` if `path` (which must live under `root`) should be
+    /// kept.
+    pub fn is_allowed(&self, root: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+
+        if relative
+            .components()
+            .any(|c| VCS_DIRS.contains(&c.as_os_str().to_string_lossy().as_ref()))
+        {
+            return false;
+        }
+
+        if let Some(gitignore) = &self.gitignore {
+            if gitignore
+                .matched_path_or_any_parents(relative, path.is_dir())
+                .is_ignore()
+            {
+                return false;
+            }
+        }
+
+        if self.excludes.is_match(relative) {
+            return false;
+        }
+
+        if !self.includes.is_empty() && !self.includes.is_match(relative) {
+            return false;
+        }
+
+        true
+    }
+}
+
+fn build_gitignore(root: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    let mut found_any = false;
+
+    for entry in WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name() == ".gitignore")
+    {
+        if builder.add(entry.path()).is_none() {
+            found_any = true;
+        }
+    }
+
+    if !found_any {
+        return None;
+    }
+
+    builder.build().ok()
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn filter(root: &Path, includes: &[&str], excludes: &[&str]) -> PathFilter {
+        let includes: Vec<String> = includes.iter().map(|s| s.to_string()).collect();
+        let excludes: Vec<String> = excludes.iter().map(|s| s.to_string()).collect();
+        PathFilter::new(root, &includes, &excludes).unwrap()
+    }
+
+    #[test]
+    fn always_skips_vcs_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let filter = filter(dir.path(), &[], &[]);
+
+        assert!(!filter.is_allowed(dir.path(), &dir.path().join(".git/objects/ab/cdef")));
+        assert!(!filter.is_allowed(dir.path(), &dir.path().join(".hg/store/data")));
+        assert!(!filter.is_allowed(dir.path(), &dir.path().join(".svn/entries")));
+        assert!(filter.is_allowed(dir.path(), &dir.path().join("src/main.rs")));
+    }
+
+    #[test]
+    fn excludes_win_over_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let filter = filter(dir.path(), &["*.rs"], &["*_test.rs"]);
+
+        assert!(filter.is_allowed(dir.path(), &dir.path().join("main.rs")));
+        assert!(!filter.is_allowed(dir.path(), &dir.path().join("main_test.rs")));
+        assert!(!filter.is_allowed(dir.path(), &dir.path().join("README.md")));
+    }
+
+    #[test]
+    fn empty_includes_match_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let filter = filter(dir.path(), &[], &["*.lock"]);
+
+        assert!(filter.is_allowed(dir.path(), &dir.path().join("main.rs")));
+        assert!(!filter.is_allowed(dir.path(), &dir.path().join("Cargo.lock")));
+    }
+
+    #[test]
+    fn gitignore_entries_are_excluded() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "target\n").unwrap();
+        let filter = filter(dir.path(), &[], &[]);
+
+        assert!(!filter.is_allowed(dir.path(), &dir.path().join("target/debug/app")));
+        assert!(filter.is_allowed(dir.path(), &dir.path().join("src/main.rs")));
+    }
+}
@@ -0,0 +1,159 @@
+use std::path::Path;
+
+/// Output format for the amalgamated file, selectable via `--format`.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// The original `// File: <path>` comment header.
+    Plain,
+    /// A `## <path>` heading followed by a fenced code block, for LLM ingestion.
+    Markdown,
+}
+
+impl OutputFormat {
+    /// The header line(s) preceding a file's body. `body` is the content
+    /// that will follow, used under `Markdown` to pick a fence longer than
+    /// any run of backticks already in the body (so a file that itself
+    /// contains a fenced example doesn't close the block early).
+    pub fn header(&self, relative_path: &Path, body: &str) -> String {
+        match self {
+            OutputFormat::Plain => format!("// File: {}\n", relative_path.display()),
+            OutputFormat::Markdown => format!(
+                "## {}\n{}{}\n",
+                relative_path.display(),
+                fence_for(body),
+                language_for(relative_path)
+            ),
+        }
+    }
+
+    /// The footer line(s) following a file's body. `body` must be the same
+    /// content passed to `header`, so the closing fence matches the
+    /// opening one.
+    pub fn footer(&self, body: &str) -> String {
+        match self {
+            OutputFormat::Plain => String::new(),
+            OutputFormat::Markdown => format!("{}\n", fence_for(body)),
+        }
+    }
+}
+
+/// A markdown fence one backtick longer than the longest run of backticks
+/// found in `body`, so the body can't contain a run that terminates the fence
+/// early. Defaults to the standard `` ``` ``.
+fn fence_for(body: &str) -> String {
+    let mut longest_run = 0;
+    let mut current_run = 0;
+    for c in body.chars() {
+        if c == '`' {
+            current_run += 1;
+            longest_run = longest_run.max(current_run);
+        } else {
+            current_run = 0;
+        }
+    }
+    "`".repeat((longest_run + 1).max(3))
+}
+
+/// Maps a file extension to the language tag used on a markdown fence.
+/// Unknown extensions fence with no language tag.
+fn language_for(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "ts" => "typescript",
+        "tsx" => "tsx",
+        "go" => "go",
+        "rb" => "ruby",
+        "java" => "java",
+        "kt" => "kotlin",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" => "cpp",
+        "cs" => "csharp",
+        "php" => "php",
+        "sh" | "bash" => "bash",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "md" => "markdown",
+        "html" => "html",
+        "css" => "css",
+        "sql" => "sql",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_header_is_a_comment_and_has_no_footer() {
+        let path = Path::new("src/main.rs");
+        assert_eq!(
+            OutputFormat::Plain.header(path, "fn main() {}"),
+            "// File: src/main.rs\n"
+        );
+        assert_eq!(OutputFormat::Plain.footer("fn main() {}"), "");
+    }
+
+    #[test]
+    fn markdown_header_fences_with_the_inferred_language() {
+        let path = Path::new("src/main.rs");
+        let body = "fn main() {}";
+        assert_eq!(
+            OutputFormat::Markdown.header(path, body),
+            "## src/main.rs\n```rust\n"
+        );
+        assert_eq!(OutputFormat::Markdown.footer(body), "```\n");
+    }
+
+    #[test]
+    fn markdown_header_fences_with_no_language_for_unknown_extensions() {
+        let path = Path::new("Dockerfile.custom");
+        assert_eq!(
+            OutputFormat::Markdown.header(path, "FROM scratch"),
+            "## Dockerfile.custom\n```\n"
+        );
+    }
+
+    #[test]
+    fn markdown_fence_escalates_past_backticks_in_the_body() {
+        let path = Path::new("README.md");
+        let body = "Here's an example:\n```rust\nfn main() {}\n```\n";
+        assert_eq!(
+            OutputFormat::Markdown.header(path, body),
+            "## README.md\n````markdown\n"
+        );
+        assert_eq!(OutputFormat::Markdown.footer(body), "````\n");
+    }
+
+    #[test]
+    fn markdown_fence_escalates_further_for_longer_runs() {
+        let body = "text with ```` four backticks";
+        assert_eq!(fence_for(body), "`````");
+    }
+
+    #[test]
+    fn language_for_covers_common_extensions() {
+        let cases = [
+            ("a.py", "python"),
+            ("a.js", "javascript"),
+            ("a.ts", "typescript"),
+            ("a.go", "go"),
+            ("a.rb", "ruby"),
+            ("a.java", "java"),
+            ("a.c", "c"),
+            ("a.cpp", "cpp"),
+            ("a.sh", "bash"),
+            ("a.json", "json"),
+            ("a.yaml", "yaml"),
+            ("a.toml", "toml"),
+            ("a.md", "markdown"),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(language_for(Path::new(name)), expected, "for {name}");
+        }
+    }
+}
@@ -1,4 +1,10 @@
-use super::action::{resolve_url, write_files, AmalgamationAction};
+use super::action::{write_files, AmalgamationAction};
+use super::budget;
+use super::content::{self, ContentKind};
+use super::filter::PathFilter;
+use super::format::OutputFormat;
+use super::preview::PreviewRenderer;
+use super::source::resolve_source;
 
 use anyhow::Result;
 use crossterm::{
@@ -16,7 +22,7 @@ use ratatui::{
 use std::{
     fs,
     io::stdout,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 pub async fn run_interactive_mode(action: AmalgamationAction) -> Result<()> {
@@ -27,25 +33,18 @@ pub async fn run_interactive_mode(action: AmalgamationAction) -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // Download and extract files first
-    let temp_dir = tempfile::TempDir::new()?;
-    let resolved_url = resolve_url(&action.url);
-    let zip_content = action.download_repository(&resolved_url).await?;
-    action.extract_zip(&zip_content, &temp_dir)?;
-
-    // Find the actual repository root directory and create a virtual root from its contents
-    let repo_root = fs::read_dir(temp_dir.path())?
-        .filter_map(Result::ok)
-        .find(|entry| entry.path().is_dir())
-        .ok_or_else(|| anyhow::anyhow!("Could not find repository root directory"))?
-        .path();
+    // Materialize the source (download+extract, or a no-op for local dirs)
+    let source = resolve_source(&action.url, action.git_ref.clone());
+    let source_root = source.materialize().await?;
+    let repo_root = source_root.path().to_path_buf();
 
     // Create file tree starting from the repository root
-    let root = FileTreeNode::new(repo_root)?;
+    let filter = PathFilter::new(&repo_root, &action.includes, &action.excludes)?;
+    let root = FileTreeNode::new(repo_root.clone(), &repo_root, &filter)?;
     let mut state = ListState::default();
     state.select(Some(0));
 
-    let result = run_app(&mut terminal, root, &mut state, &action);
+    let result = run_app(&mut terminal, root, &mut state, &action, &repo_root);
 
     // Restore terminal
     disable_raw_mode()?;
@@ -59,8 +58,10 @@ fn run_app<B: ratatui::backend::Backend>(
     mut root: FileTreeNode,
     state: &mut ListState,
     action: &AmalgamationAction,
+    repo_root: &Path,
 ) -> Result<()> {
     let mut focus_on_tree = true;
+    let mut preview_renderer = PreviewRenderer::new();
 
     loop {
         terminal.draw(|f| {
@@ -73,16 +74,35 @@ fn run_app<B: ratatui::backend::Backend>(
                 ])
                 .split(f.size());
 
+            let panes = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .split(chunks[0]);
+
             // File tree
             let items: Vec<ListItem> = create_tree_items(&root);
             let list = List::new(items)
                 .block(Block::default().borders(Borders::ALL).title("Files"))
                 .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
-            f.render_stateful_widget(list, chunks[0], state);
+            f.render_stateful_widget(list, panes[0], state);
 
-            // Instructions
-            let help_text =
-                "TAB: Switch focus | SPACE: Select/Deselect | ENTER: Expand/Collapse | ESC: Exit";
+            // Preview of the currently highlighted file
+            let preview_text = match selected_node(&root, state.selected().unwrap_or(0)) {
+                Some(node) if !node.is_dir => {
+                    let height = panes[1].height.saturating_sub(2) as usize;
+                    preview_renderer.render(&node.path, height)
+                }
+                _ => ratatui::text::Text::from("(select a file to preview)"),
+            };
+            let preview = Paragraph::new(preview_text)
+                .block(Block::default().borders(Borders::ALL).title("Preview"));
+            f.render_widget(preview, panes[1]);
+
+            // Instructions, plus a running total of selected tokens
+            let help_text = format!(
+                "TAB: Switch focus | SPACE: Select/Deselect | ENTER: Expand/Collapse | ESC: Exit | Selected: ~{} tokens",
+                root.selected_token_estimate()
+            );
             let help = Paragraph::new(help_text).block(Block::default().borders(Borders::ALL));
             f.render_widget(help, chunks[1]);
 
@@ -110,7 +130,13 @@ fn run_app<B: ratatui::backend::Backend>(
                         }
                     } else {
                         // OK button pressed - process selected files
-                        if let Err(e) = root.write_selected_files(&action.output_pathname) {
+                        if let Err(e) = root.write_selected_files(
+                            &action.output_pathname,
+                            action.skip_binary,
+                            action.format,
+                            repo_root,
+                            action.max_tokens,
+                        ) {
                             eprintln!("Error writing files: {}", e);
                         }
                         return Ok(());
@@ -151,16 +177,40 @@ pub struct FileTreeNode {
     is_selected: bool,
     is_expanded: bool,
     children: Vec<FileTreeNode>,
+    /// Estimated token count: the file's own estimate, or the sum of its
+    /// children's for a directory.
+    token_estimate: u64,
+}
+
+/// Estimates a file's token cost the same way `action::write_files` charges
+/// it against `--max-tokens`: text files by their raw size, binary files by
+/// their base64-encoded size, so the TUI's live total agrees with what the
+/// non-interactive writer will actually emit.
+fn estimate_file_tokens(path: &Path) -> u64 {
+    let Ok(bytes) = fs::read(path) else {
+        return 0;
+    };
+    let is_text = matches!(content::classify(path, &bytes), ContentKind::Text)
+        && std::str::from_utf8(&bytes).is_ok();
+    if is_text {
+        budget::estimate_tokens(bytes.len() as u64)
+    } else {
+        budget::estimate_tokens(budget::base64_encoded_len(bytes.len()) as u64)
+    }
 }
 
 impl FileTreeNode {
-    fn new(path: PathBuf) -> Result<Self> {
+    fn new(path: PathBuf, root: &Path, filter: &PathFilter) -> Result<Self> {
         let is_dir = path.is_dir();
         let children = if is_dir {
             let mut children = Vec::new();
             for entry in fs::read_dir(&path)? {
                 let entry = entry?;
-                children.push(FileTreeNode::new(entry.path())?);
+                let child_path = entry.path();
+                if !filter.is_allowed(root, &child_path) {
+                    continue;
+                }
+                children.push(FileTreeNode::new(child_path, root, filter)?);
             }
             children.sort_by_key(|node| (node.is_dir, node.path.to_string_lossy().into_owned()));
             children
@@ -168,12 +218,19 @@ impl FileTreeNode {
             Vec::new()
         };
 
+        let token_estimate = if is_dir {
+            children.iter().map(|child| child.token_estimate).sum()
+        } else {
+            estimate_file_tokens(&path)
+        };
+
         Ok(FileTreeNode {
             path,
             is_dir,
             is_selected: true,
             is_expanded: false,
             children,
+            token_estimate,
         })
     }
 
@@ -190,13 +247,43 @@ impl FileTreeNode {
         selected
     }
 
-    fn write_selected_files(&self, output_path: &PathBuf) -> Result<()> {
+    /// Sums `token_estimate` over currently-selected files, for the live
+    /// status-line total.
+    fn selected_token_estimate(&self) -> u64 {
+        if !self.is_dir && self.is_selected {
+            return self.token_estimate;
+        }
+        if self.is_dir {
+            return self
+                .children
+                .iter()
+                .map(|child| child.selected_token_estimate())
+                .sum();
+        }
+        0
+    }
+
+    fn write_selected_files(
+        &self,
+        output_path: &PathBuf,
+        skip_binary: bool,
+        format: OutputFormat,
+        root: &Path,
+        max_tokens: Option<u64>,
+    ) -> Result<()> {
         let selected_files = self.collect_selected_files();
         if selected_files.is_empty() {
             return Ok(());
         }
 
-        write_files(&selected_files, output_path)
+        write_files(
+            &selected_files,
+            output_path,
+            skip_binary,
+            format,
+            root,
+            max_tokens,
+        )
     }
 }
 
@@ -217,8 +304,8 @@ fn create_tree_items(node: &FileTreeNode) -> Vec<ListItem> {
         let name = node.path.file_name().unwrap_or_default().to_string_lossy();
 
         items.push(ListItem::new(format!(
-            "{}{}{} {}",
-            prefix, icon, checkbox, name
+            "{}{}{} {} (~{} tok)",
+            prefix, icon, checkbox, name, node.token_estimate
         )));
 
         if node.is_expanded {
@@ -233,6 +320,27 @@ fn create_tree_items(node: &FileTreeNode) -> Vec<ListItem> {
     items
 }
 
+fn selected_node(root: &FileTreeNode, index: usize) -> Option<&FileTreeNode> {
+    fn find<'a>(node: &'a FileTreeNode, index: &mut usize) -> Option<&'a FileTreeNode> {
+        if *index == 0 {
+            return Some(node);
+        }
+        *index -= 1;
+
+        if node.is_expanded {
+            for child in &node.children {
+                if let Some(found) = find(child, index) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+
+    let mut current_index = index;
+    find(root, &mut current_index)
+}
+
 fn count_visible_nodes(node: &FileTreeNode) -> usize {
     let mut count = 1;
     if node.is_expanded {
@@ -2,13 +2,19 @@ use clap::Parser;
 use std::path::PathBuf;
 
 mod action;
+mod budget;
+mod content;
+mod filter;
+mod format;
 mod interactive;
+mod preview;
 mod progress;
+mod source;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// GitHub repository URL
+    /// Repository URL (GitHub, GitLab) or a local directory path
     url: String,
 
     /// Output file path
@@ -22,6 +28,30 @@ struct Cli {
     /// Verbose output
     #[arg(short, long)]
     verbose: bool,
+
+    /// Glob pattern of files to include (repeatable, empty = match all)
+    #[arg(long = "include")]
+    includes: Vec<String>,
+
+    /// Glob pattern of files to exclude (repeatable, takes priority over includes)
+    #[arg(long = "exclude")]
+    excludes: Vec<String>,
+
+    /// Omit binary and image files from the output instead of base64-encoding them
+    #[arg(long)]
+    skip_binary: bool,
+
+    /// Branch, tag, or commit to fetch (forge sources only; defaults to the repo's default branch)
+    #[arg(long = "ref")]
+    git_ref: Option<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "plain")]
+    format: format::OutputFormat,
+
+    /// Stop writing once the estimated token count would exceed this budget
+    #[arg(long)]
+    max_tokens: Option<u64>,
 }
 
 #[tokio::main]
@@ -32,6 +62,12 @@ async fn main() -> anyhow::Result<()> {
         url: cli.url,
         output_pathname: cli.output,
         verbose: cli.verbose,
+        includes: cli.includes,
+        excludes: cli.excludes,
+        skip_binary: cli.skip_binary,
+        git_ref: cli.git_ref,
+        format: cli.format,
+        max_tokens: cli.max_tokens,
     };
 
     if cli.interactive {
@@ -0,0 +1,73 @@
+use ansi_to_tui::IntoText;
+use anyhow::Result;
+use ratatui::text::Text;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+/// Renders syntax-highlighted file previews for the interactive tree.
+///
+/// Highlighted output is cached by `(path, height)`, so redrawing the
+/// preview for a node the user has already visited at the same viewport
+/// size (e.g. moving the cursor away and back) is a cache hit rather than a
+/// re-highlight. Keying on height too means a terminal resize invalidates
+/// the cache instead of replaying a stale, too-short highlight.
+pub struct PreviewRenderer {
+    syntax_set: SyntaxSet,
+    theme: Theme,
+    cache: HashMap<(PathBuf, usize), Text<'static>>,
+}
+
+impl PreviewRenderer {
+    pub fn new() -> Self {
+        let syntax_set = SyntaxSet::load_defaults_newlines();
+        let theme_set = ThemeSet::load_defaults();
+        let theme = theme_set.themes["base16-ocean.dark"].clone();
+        Self {
+            syntax_set,
+            theme,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Renders (or returns the cached rendering of) `path`, highlighting at
+    /// most `height` lines so large files stay responsive.
+    pub fn render(&mut self, path: &Path, height: usize) -> Text<'static> {
+        let key = (path.to_path_buf(), height);
+        if let Some(cached) = self.cache.get(&key) {
+            return cached.clone();
+        }
+
+        let text = self
+            .highlight(path, height)
+            .unwrap_or_else(|e| Text::from(format!("(could not preview: {})", e)));
+
+        self.cache.insert(key, text.clone());
+        text
+    }
+
+    fn highlight(&self, path: &Path, height: usize) -> Result<Text<'static>> {
+        let content = fs::read_to_string(path)?;
+
+        let syntax = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.syntax_set.find_syntax_by_extension(ext))
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+
+        let mut highlighter = HighlightLines::new(syntax, &self.theme);
+        let mut ansi_output = String::new();
+        for line in LinesWithEndings::from(&content).take(height) {
+            let ranges = highlighter.highlight_line(line, &self.syntax_set)?;
+            ansi_output.push_str(&as_24_bit_terminal_escaped(&ranges[..], false));
+        }
+        // Reset so a trailing style doesn't bleed into the rest of the UI.
+        ansi_output.push_str("\x1b[0m");
+
+        Ok(ansi_output.into_text()?)
+    }
+}
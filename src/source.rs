@@ -0,0 +1,240 @@
+use crate::action::resolve_url;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use zip::ZipArchive;
+
+/// Materializes a repository (or plain directory) into a root directory
+/// that can be walked and amalgamated, independent of where it lives.
+#[async_trait]
+pub trait Source {
+    async fn materialize(&self) -> Result<SourceRoot>;
+}
+
+/// The root to walk once a `Source` has produced it.
+///
+/// Archive-backed sources own the `TempDir` they extracted into, which must
+/// stay alive for as long as `path()` is used; local directories need no
+/// cleanup at all.
+pub enum SourceRoot {
+    Archive { _temp_dir: TempDir, root: PathBuf },
+    Local(PathBuf),
+}
+
+impl SourceRoot {
+    pub fn path(&self) -> &Path {
+        match self {
+            SourceRoot::Archive { root, .. } => root,
+            SourceRoot::Local(root) => root,
+        }
+    }
+}
+
+/// Downloads a GitHub repository as a zipball for a given ref (branch, tag,
+/// or commit), defaulting to the repository's default branch.
+pub struct GitHubZipSource {
+    url: String,
+    git_ref: String,
+}
+
+impl GitHubZipSource {
+    pub fn new(resolved_url: String, git_ref: Option<String>) -> Self {
+        Self {
+            url: resolved_url,
+            git_ref: git_ref.unwrap_or_else(|| "HEAD".to_string()),
+        }
+    }
+
+    fn archive_url(&self) -> String {
+        format!("{}/zipball/{}", self.url, self.git_ref)
+    }
+}
+
+#[async_trait]
+impl Source for GitHubZipSource {
+    async fn materialize(&self) -> Result<SourceRoot> {
+        download_and_extract(&self.archive_url()).await
+    }
+}
+
+/// Downloads a GitLab project's archive for a given ref via its `-/archive`
+/// endpoint.
+pub struct GitLabSource {
+    url: String,
+    git_ref: String,
+}
+
+impl GitLabSource {
+    pub fn new(resolved_url: String, git_ref: Option<String>) -> Self {
+        Self {
+            url: resolved_url,
+            git_ref: git_ref.unwrap_or_else(|| "HEAD".to_string()),
+        }
+    }
+
+    fn archive_url(&self) -> String {
+        format!("{}/-/archive/{}/archive.zip", self.url, self.git_ref)
+    }
+}
+
+#[async_trait]
+impl Source for GitLabSource {
+    async fn materialize(&self) -> Result<SourceRoot> {
+        download_and_extract(&self.archive_url()).await
+    }
+}
+
+/// A plain directory already on disk; used as-is with no download or
+/// extraction step.
+pub struct LocalDirSource {
+    root: PathBuf,
+}
+
+impl LocalDirSource {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+#[async_trait]
+impl Source for LocalDirSource {
+    async fn materialize(&self) -> Result<SourceRoot> {
+        Ok(SourceRoot::Local(self.root.clone()))
+    }
+}
+
+async fn download_and_extract(archive_url: &str) -> Result<SourceRoot> {
+    let client = Client::new();
+    let response = client.get(archive_url).send().await?;
+    let content = response.bytes().await?;
+
+    let temp_dir = TempDir::new()?;
+    extract_zip(&content, temp_dir.path())?;
+
+    // Forge archives nest everything under a single top-level directory
+    // (e.g. `owner-repo-<sha>/`); that's the real root to walk.
+    let root = fs::read_dir(temp_dir.path())?
+        .filter_map(Result::ok)
+        .find(|entry| entry.path().is_dir())
+        .map(|entry| entry.path())
+        .ok_or_else(|| anyhow!("Could not find repository root directory in archive"))?;
+
+    Ok(SourceRoot::Archive {
+        _temp_dir: temp_dir,
+        root,
+    })
+}
+
+fn extract_zip(zip_content: &[u8], dest: &Path) -> Result<()> {
+    let reader = std::io::Cursor::new(zip_content);
+    let mut archive = ZipArchive::new(reader)?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        // `enclosed_name()` rejects `..` components and absolute paths, so a
+        // malicious entry can't escape `dest` (zip-slip).
+        let Some(relative) = file.enclosed_name() else {
+            return Err(anyhow!(
+                "Archive entry {:?} has an unsafe path, refusing to extract",
+                file.name()
+            ));
+        };
+        let outpath = dest.join(relative);
+
+        if file.name().ends_with('/') {
+            fs::create_dir_all(&outpath)?;
+        } else {
+            if let Some(p) = outpath.parent() {
+                fs::create_dir_all(p)?;
+            }
+            let mut outfile = File::create(&outpath)?;
+            std::io::copy(&mut file, &mut outfile)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Detects which forge (or local filesystem path) `url` refers to and
+/// returns the matching `Source`.
+pub fn resolve_source(url: &str, git_ref: Option<String>) -> Box<dyn Source> {
+    if Path::new(url).is_dir() {
+        return Box::new(LocalDirSource::new(PathBuf::from(url)));
+    }
+
+    let resolved = resolve_url(url);
+    if is_gitlab_url(&resolved) {
+        Box::new(GitLabSource::new(resolved, git_ref))
+    } else {
+        Box::new(GitHubZipSource::new(resolved, git_ref))
+    }
+}
+
+fn is_gitlab_url(resolved_url: &str) -> bool {
+    resolved_url.contains("gitlab.com")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_gitlab_url_detects_gitlab_only() {
+        assert!(is_gitlab_url("https://gitlab.com/owner/repo"));
+        assert!(!is_gitlab_url("https://github.com/owner/repo"));
+        assert!(!is_gitlab_url("https://example.com/owner/repo"));
+    }
+
+    #[tokio::test]
+    async fn resolve_source_dispatches_local_dirs_without_downloading() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = resolve_source(dir.path().to_str().unwrap(), None);
+        let root = source.materialize().await.unwrap();
+        assert_eq!(root.path(), dir.path());
+    }
+
+    #[test]
+    fn github_archive_url_defaults_to_head_without_a_ref() {
+        let source = GitHubZipSource::new("https://github.com/owner/repo".to_string(), None);
+        assert_eq!(
+            source.archive_url(),
+            "https://github.com/owner/repo/zipball/HEAD"
+        );
+    }
+
+    #[test]
+    fn github_archive_url_uses_the_explicit_ref() {
+        let source = GitHubZipSource::new(
+            "https://github.com/owner/repo".to_string(),
+            Some("v1.2.3".to_string()),
+        );
+        assert_eq!(
+            source.archive_url(),
+            "https://github.com/owner/repo/zipball/v1.2.3"
+        );
+    }
+
+    #[test]
+    fn gitlab_archive_url_defaults_to_head_without_a_ref() {
+        let source = GitLabSource::new("https://gitlab.com/owner/repo".to_string(), None);
+        assert_eq!(
+            source.archive_url(),
+            "https://gitlab.com/owner/repo/-/archive/HEAD/archive.zip"
+        );
+    }
+
+    #[test]
+    fn gitlab_archive_url_uses_the_explicit_ref() {
+        let source = GitLabSource::new(
+            "https://gitlab.com/owner/repo".to_string(),
+            Some("main".to_string()),
+        );
+        assert_eq!(
+            source.archive_url(),
+            "https://gitlab.com/owner/repo/-/archive/main/archive.zip"
+        );
+    }
+}